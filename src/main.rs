@@ -2,43 +2,146 @@ use chrono::{DateTime, FixedOffset};
 use rss::Channel;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::error::Error;
 use std::fs;
 use std::io::Read;
+use std::sync::{mpsc, Arc, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use strfmt::strfmt;
 
 const CHECK_INTERVAL_SECONDS: u64 = 300;
 const STATE_FILE: &str = "state.json";
+const CONFIG_FILE: &str = "config.json";
+const MAX_SEEN_GUIDS_PER_FEED: usize = 500;
+const DEFAULT_TITLE_FORMAT: &str = "[{feed}] {title}";
+const DEFAULT_DESCRIPTION_FORMAT: &str = "{description}";
+const DESCRIPTION_MAX_CHARS: usize = 200;
+const DEFAULT_WORKER_THREADS: usize = 4;
+const DEFAULT_REQUEST_TIMEOUT_SECONDS: u64 = 15;
+const DISCORD_RATE_LIMIT: Duration = Duration::from_millis(1000);
+
+/// Which parser a feed's `url` should be fetched with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum FeedKind {
+    Rss,
+    Atom,
+    Steam,
+}
 
-#[derive(Debug)]
+impl Default for FeedKind {
+    fn default() -> Self {
+        FeedKind::Rss
+    }
+}
+
+#[derive(Debug, Deserialize)]
 struct FeedConfig {
-    url: &'static str,
+    url: String,
     color: u32,
+    #[serde(default)]
+    kind: FeedKind,
+    #[serde(default)]
+    webhook_url: Option<String>,
+    #[serde(default)]
+    username: Option<String>,
+    #[serde(default)]
+    avatar_url: Option<String>,
+    #[serde(default)]
+    refresh_seconds: Option<u64>,
+    #[serde(default = "default_title_format")]
+    title_format: String,
+    #[serde(default = "default_description_format")]
+    description_format: String,
+}
+
+fn default_title_format() -> String {
+    DEFAULT_TITLE_FORMAT.to_string()
+}
+
+fn default_description_format() -> String {
+    DEFAULT_DESCRIPTION_FORMAT.to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct Config {
+    feeds: Vec<FeedConfig>,
+    #[serde(default)]
+    http: Option<HttpConfig>,
+    #[serde(default)]
+    worker_threads: Option<usize>,
+    #[serde(default = "default_request_timeout_seconds")]
+    request_timeout_seconds: u64,
+}
+
+fn default_request_timeout_seconds() -> u64 {
+    DEFAULT_REQUEST_TIMEOUT_SECONDS
+}
+
+/// Settings for the optional bundled-feed HTTP endpoint.
+#[derive(Debug, Clone, Deserialize)]
+struct HttpConfig {
+    host: String,
+    port: u16,
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    link: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+impl Config {
+    /// Loads the config file named by the `--config` CLI arg, the `CONFIG_FILE`
+    /// env var, or `config.json`, in that order.
+    fn load() -> Result<Self, Box<dyn Error>> {
+        let path = Self::path();
+        let data = fs::read_to_string(&path)
+            .map_err(|e| format!("failed to read config file {}: {}", path, e))?;
+        let config: Config = serde_json::from_str(&data)
+            .map_err(|e| format!("failed to parse config file {}: {}", path, e))?;
+        Ok(config)
+    }
+
+    fn path() -> String {
+        let mut args = env::args();
+        while let Some(arg) = args.next() {
+            if arg == "--config" {
+                if let Some(path) = args.next() {
+                    return path;
+                }
+            }
+        }
+        env::var("CONFIG_FILE").unwrap_or_else(|_| CONFIG_FILE.to_string())
+    }
 }
 
-const FEEDS: &[FeedConfig] = &[
-    FeedConfig {
-        url: "https://archlinux.org/feeds/packages/x86_64/core/",
-        color: 1791981, // Blue
-    },
-    FeedConfig {
-        url: "https://archlinux.org/feeds/news/",
-        color: 13438481, // Red
-    },
-];
+/// A seen item's GUID plus the date it carried (if any), kept in
+/// insertion order so the oldest entries can be pruned without relying on
+/// a `HashSet`'s arbitrary iteration order.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SeenGuid {
+    guid: String,
+    date: Option<DateTime<FixedOffset>>,
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct AppState {
     last_seen: HashMap<String, DateTime<FixedOffset>>,
+    /// GUIDs (or link, when a feed omits `guid`) already seen per feed URL,
+    /// whether delivered or skipped on the first poll, so novelty no longer depends solely on `pubDate` comparisons.
+    #[serde(default)]
+    seen_guids: HashMap<String, Vec<SeenGuid>>,
 }
 
 impl AppState {
     fn new() -> Self {
         AppState {
             last_seen: HashMap::new(),
+            seen_guids: HashMap::new(),
         }
     }
 
@@ -60,6 +163,8 @@ impl AppState {
 #[derive(Serialize)]
 struct DiscordMessage<'a> {
     username: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    avatar_url: Option<Cow<'a, str>>,
     embeds: Vec<DiscordEmbed<'a>>,
 }
 
@@ -70,7 +175,10 @@ struct DiscordEmbed<'a> {
     description: Cow<'a, str>,
     color: u32,
     footer: DiscordFooter<'a>,
-    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<DiscordEmbedImage>,
 }
 
 #[derive(Serialize)]
@@ -78,144 +186,630 @@ struct DiscordFooter<'a> {
     text: Cow<'a, str>,
 }
 
+#[derive(Serialize)]
+struct DiscordEmbedImage {
+    url: String,
+}
+
+/// A feed item normalized to a common shape, regardless of which
+/// `FeedSource` produced it.
+#[derive(Debug, Clone)]
+struct NewsItem {
+    title: String,
+    link: String,
+    description: String,
+    author: String,
+    date: Option<DateTime<FixedOffset>>,
+    guid: String,
+}
+
+/// A fetched feed's items plus the channel/feed title they belong to.
+struct FetchedFeed {
+    title: String,
+    items: Vec<NewsItem>,
+}
+
+/// Fetches a feed and normalizes its entries into `NewsItem`s. Implementors
+/// do no state mutation, so they're safe to run from a worker thread.
+trait FeedSource {
+    fn fetch(
+        &self,
+        feed_config: &FeedConfig,
+        timeout: Duration,
+    ) -> Result<FetchedFeed, Box<dyn Error + Send + Sync>>;
+}
+
+struct RssFeedSource;
+
+impl FeedSource for RssFeedSource {
+    fn fetch(
+        &self,
+        feed_config: &FeedConfig,
+        timeout: Duration,
+    ) -> Result<FetchedFeed, Box<dyn Error + Send + Sync>> {
+        let content = fetch_bytes(feed_config, timeout)?;
+        let channel = Channel::read_from(&content[..])?;
+        let items = channel.items().iter().map(news_item_from_rss_item).collect();
+        Ok(FetchedFeed {
+            title: channel.title,
+            items,
+        })
+    }
+}
+
+struct AtomFeedSource;
+
+impl FeedSource for AtomFeedSource {
+    fn fetch(
+        &self,
+        feed_config: &FeedConfig,
+        timeout: Duration,
+    ) -> Result<FetchedFeed, Box<dyn Error + Send + Sync>> {
+        let content = fetch_bytes(feed_config, timeout)?;
+        let feed = atom_syndication::Feed::read_from(&content[..])?;
+        let items = feed
+            .entries()
+            .iter()
+            .map(news_item_from_atom_entry)
+            .collect();
+        Ok(FetchedFeed {
+            title: feed.title().value.clone(),
+            items,
+        })
+    }
+}
+
+struct SteamFeedSource;
+
+impl FeedSource for SteamFeedSource {
+    fn fetch(
+        &self,
+        feed_config: &FeedConfig,
+        timeout: Duration,
+    ) -> Result<FetchedFeed, Box<dyn Error + Send + Sync>> {
+        let content = fetch_bytes(feed_config, timeout)?;
+        let response: SteamNewsResponse = serde_json::from_slice(&content)?;
+        let items = response
+            .appnews
+            .newsitems
+            .into_iter()
+            .map(news_item_from_steam_item)
+            .collect();
+        Ok(FetchedFeed {
+            title: "Steam News".to_string(),
+            items,
+        })
+    }
+}
+
+/// The bits of Steam's `ISteamNews/GetNewsForApp` response shape that we use.
+#[derive(Debug, Deserialize)]
+struct SteamNewsResponse {
+    appnews: SteamAppNews,
+}
+
+#[derive(Debug, Deserialize)]
+struct SteamAppNews {
+    newsitems: Vec<SteamNewsItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SteamNewsItem {
+    gid: String,
+    title: String,
+    url: String,
+    author: String,
+    contents: String,
+    date: i64,
+}
+
+fn feed_source(kind: FeedKind) -> Box<dyn FeedSource> {
+    match kind {
+        FeedKind::Rss => Box::new(RssFeedSource),
+        FeedKind::Atom => Box::new(AtomFeedSource),
+        FeedKind::Steam => Box::new(SteamFeedSource),
+    }
+}
+
+fn fetch_bytes(
+    feed_config: &FeedConfig,
+    timeout: Duration,
+) -> Result<Vec<u8>, Box<dyn Error + Send + Sync>> {
+    let response = ureq::get(&feed_config.url).timeout(timeout).call()?;
+    let mut content = Vec::new();
+    response.into_reader().read_to_end(&mut content)?;
+    Ok(content)
+}
+
+fn news_item_from_rss_item(item: &rss::Item) -> NewsItem {
+    NewsItem {
+        title: item.title().unwrap_or("No Title").to_string(),
+        link: item.link().unwrap_or("").to_string(),
+        description: item.description().unwrap_or("").to_string(),
+        author: item.author().unwrap_or("").to_string(),
+        date: resolve_item_date(item),
+        guid: item_guid(item),
+    }
+}
+
+/// Resolves an RSS item's date, since feeds disagree on both field and
+/// format: tries `pubDate` as RFC 2822, then the Dublin Core `dc:date`
+/// extension (as RFC 3339), then falls back to parsing `pubDate` as RFC 3339
+/// in case it was written non-conformantly.
+fn resolve_item_date(item: &rss::Item) -> Option<DateTime<FixedOffset>> {
+    item.pub_date()
+        .and_then(|s| DateTime::parse_from_rfc2822(s).ok())
+        .or_else(|| {
+            item.dublin_core_ext()
+                .and_then(|dc| dc.dates().first())
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        })
+        .or_else(|| {
+            item.pub_date()
+                .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        })
+}
+
+fn news_item_from_atom_entry(entry: &atom_syndication::Entry) -> NewsItem {
+    let link = entry
+        .links()
+        .first()
+        .map(|link| link.href().to_string())
+        .unwrap_or_default();
+    let description = entry
+        .summary()
+        .map(|text| text.value.clone())
+        .or_else(|| entry.content().and_then(|content| content.value.clone()))
+        .unwrap_or_default();
+    let author = entry
+        .authors()
+        .first()
+        .map(|person| person.name().to_string())
+        .unwrap_or_default();
+    let date = entry.published().copied().or_else(|| Some(*entry.updated()));
+
+    NewsItem {
+        title: entry.title().value.clone(),
+        link,
+        description,
+        author,
+        date,
+        guid: entry.id().to_string(),
+    }
+}
+
+fn news_item_from_steam_item(item: SteamNewsItem) -> NewsItem {
+    let date = DateTime::from_timestamp(item.date, 0).map(|dt| dt.fixed_offset());
+    NewsItem {
+        title: item.title,
+        link: item.url,
+        description: item.contents,
+        author: item.author,
+        date,
+        guid: item.gid,
+    }
+}
+
+/// Latest fetched items per feed URL, shared between the poll loop and the
+/// HTTP server thread.
+type AggregatedFeeds = Arc<Mutex<HashMap<String, Vec<NewsItem>>>>;
+
+fn update_aggregated_feed(aggregated: &AggregatedFeeds, feed_config: &FeedConfig, items: &[NewsItem]) {
+    aggregated
+        .lock()
+        .unwrap()
+        .insert(feed_config.url.clone(), items.to_vec());
+}
+
+/// Merges every feed's latest items into one GUID-deduplicated, date-sorted
+/// `Channel`.
+fn build_aggregate_channel(http_config: &HttpConfig, aggregated: &AggregatedFeeds) -> Channel {
+    let store = aggregated.lock().unwrap();
+
+    let mut seen_guids = HashSet::new();
+    let mut items: Vec<&NewsItem> = Vec::new();
+    for feed_items in store.values() {
+        for item in feed_items {
+            if seen_guids.insert(item.guid.clone()) {
+                items.push(item);
+            }
+        }
+    }
+    items.sort_by(|a, b| b.date.cmp(&a.date));
+
+    let mut channel = Channel::default();
+    channel.title = http_config
+        .title
+        .clone()
+        .unwrap_or_else(|| "Aggregated Feed".to_string());
+    channel.link = http_config.link.clone().unwrap_or_default();
+    channel.description = http_config.description.clone().unwrap_or_default();
+    channel.items = items
+        .into_iter()
+        .map(|item| {
+            let mut rss_item = rss::Item::default();
+            rss_item.title = Some(item.title.clone());
+            rss_item.link = Some(item.link.clone());
+            rss_item.description = Some(item.description.clone());
+            rss_item.pub_date = item.date.map(|date| date.to_rfc2822());
+            rss_item.guid = Some(rss::Guid {
+                value: item.guid.clone(),
+                permalink: false,
+            });
+            rss_item
+        })
+        .collect();
+    channel
+}
+
+/// Serves the bundled feed as RSS XML over HTTP; runs for the lifetime of
+/// the process on its own thread.
+fn start_http_server(http_config: HttpConfig, aggregated: AggregatedFeeds) {
+    let addr = format!("{}:{}", http_config.host, http_config.port);
+    let server = match tiny_http::Server::http(&addr) {
+        Ok(server) => server,
+        Err(e) => {
+            eprintln!("Failed to start HTTP server on {}: {}", addr, e);
+            return;
+        }
+    };
+    println!("Serving aggregated feed at http://{}", addr);
+
+    for request in server.incoming_requests() {
+        let channel = build_aggregate_channel(&http_config, &aggregated);
+        let header = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/rss+xml"[..])
+            .expect("static header is valid");
+        let response = tiny_http::Response::from_string(channel.to_string()).with_header(header);
+        if let Err(e) = request.respond(response) {
+            eprintln!("Failed to respond to HTTP request: {}", e);
+        }
+    }
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let webhook_url = env::var("DISCORD_WEBHOOK_URL")
         .expect("DISCORD_WEBHOOK_URL must be set in .env or environment");
 
+    let config = Config::load()?;
     println!("RSS webhook started.");
-    println!("Monitoring feeds: {:?}", FEEDS);
+    println!("Monitoring feeds: {:?}", config.feeds);
+
+    let aggregated: AggregatedFeeds = Arc::new(Mutex::new(HashMap::new()));
+    if let Some(http_config) = config.http.clone() {
+        let aggregated = Arc::clone(&aggregated);
+        thread::spawn(move || start_http_server(http_config, aggregated));
+    }
+
+    // Feeds may override the global check interval, so each is tracked
+    // separately and the main loop just wakes up on the shortest one.
+    let mut next_poll: HashMap<String, Instant> = HashMap::new();
+    let tick = config
+        .feeds
+        .iter()
+        .map(|feed| feed.refresh_seconds.unwrap_or(CHECK_INTERVAL_SECONDS))
+        .min()
+        .unwrap_or(CHECK_INTERVAL_SECONDS);
+
+    let worker_threads = config.worker_threads.unwrap_or(DEFAULT_WORKER_THREADS).max(1);
+    let request_timeout = Duration::from_secs(config.request_timeout_seconds);
 
     loop {
         let mut state = AppState::load();
         let mut state_changed = false;
-
-        for feed in FEEDS {
-            match fetch_and_process_feed(feed, &mut state, &webhook_url) {
-                Ok(updated) => {
-                    if updated {
-                        state_changed = true;
+        let mut last_sent: HashMap<String, Instant> = HashMap::new();
+        let now = Instant::now();
+
+        let due_feeds: Vec<&FeedConfig> = config
+            .feeds
+            .iter()
+            .filter(|feed| next_poll.get(&feed.url).map_or(true, |&due| now >= due))
+            .collect();
+
+        for (feed, fetch_result) in poll_feeds(&due_feeds, worker_threads, request_timeout) {
+            match fetch_result {
+                Ok(fetched) => {
+                    let feed_webhook = feed.webhook_url.as_deref().unwrap_or(&webhook_url);
+                    match process_feed_items(
+                        feed,
+                        fetched,
+                        &mut state,
+                        feed_webhook,
+                        &aggregated,
+                        &mut last_sent,
+                    ) {
+                        Ok(updated) => {
+                            if updated {
+                                state_changed = true;
+                            }
+                        }
+                        Err(e) => eprintln!("Error processing {}: {}", feed.url, e),
                     }
                 }
-                Err(e) => eprintln!("Error processing {}: {}", feed.url, e),
+                Err(e) => eprintln!("Error fetching {}: {}", feed.url, e),
             }
+
+            let interval = feed.refresh_seconds.unwrap_or(CHECK_INTERVAL_SECONDS);
+            next_poll.insert(feed.url.clone(), now + Duration::from_secs(interval));
         }
 
         if state_changed {
             state.save();
         }
 
-        thread::sleep(Duration::from_secs(CHECK_INTERVAL_SECONDS));
+        thread::sleep(Duration::from_secs(tick));
     }
 }
 
-fn fetch_and_process_feed(
-    feed_config: &FeedConfig,
-    state: &mut AppState,
-    webhook_url: &str,
-) -> Result<bool, Box<dyn Error>> {
-    let response = ureq::get(feed_config.url).call()?;
-    let mut content = Vec::new();
-    response.into_reader().read_to_end(&mut content)?;
+/// Fetches every due feed in parallel across a bounded worker pool, so a
+/// single slow feed only holds up the others assigned to the same worker.
+/// Results are collected into a `Vec` and handed back once every worker has
+/// finished, so the caller still processes the whole batch at once rather
+/// than as each fetch completes.
+fn poll_feeds<'cfg>(
+    feeds: &[&'cfg FeedConfig],
+    worker_threads: usize,
+    timeout: Duration,
+) -> Vec<(&'cfg FeedConfig, Result<FetchedFeed, String>)> {
+    if feeds.is_empty() {
+        return Vec::new();
+    }
 
-    let channel = Channel::read_from(&content[..])?;
+    let (tx, rx) = mpsc::channel();
+    let chunk_size = ((feeds.len() + worker_threads - 1) / worker_threads).max(1);
+
+    thread::scope(|scope| {
+        for chunk in feeds.chunks(chunk_size) {
+            let tx = tx.clone();
+            scope.spawn(move || {
+                for feed in chunk {
+                    let result = feed_source(feed.kind)
+                        .fetch(feed, timeout)
+                        .map_err(|e| e.to_string());
+                    let _ = tx.send((*feed, result));
+                }
+            });
+        }
+        drop(tx);
 
-    let mut items_with_dates: Vec<(&rss::Item, DateTime<FixedOffset>)> = Vec::new();
-    for item in channel.items() {
-        if let Some(pub_date_str) = item.pub_date() {
-            if let Ok(date) = DateTime::parse_from_rfc2822(pub_date_str) {
-                items_with_dates.push((item, date));
-            }
+        rx.into_iter().collect()
+    })
+}
+
+/// Strips HTML tags and decodes entities, leaving plain text suitable for an
+/// embed description. A naive `<`/`>` scanner rather than a real parser, so
+/// it mishandles a literal `>` inside an attribute value or CDATA section —
+/// acceptable for the mostly-well-formed descriptions feeds send.
+fn clean_html(html: &str) -> String {
+    let mut stripped = String::with_capacity(html.len());
+    let mut inside_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => inside_tag = true,
+            '>' => inside_tag = false,
+            _ if !inside_tag => stripped.push(c),
+            _ => {}
         }
     }
+    html_escape::decode_html_entities(&stripped).trim().to_string()
+}
 
-    let last_seen_option = state.last_seen.get(feed_config.url);
+/// Pulls the `src` attribute of the first `<img>` tag out of an HTML
+/// fragment, if any. The search is confined to that tag's own attributes
+/// (not whatever follows it) and requires `src=` to start a fresh
+/// attribute, so `data-src=`/`data-srcset=` lazy-load placeholders aren't
+/// mistaken for the real attribute.
+fn extract_first_image(html: &str) -> Option<String> {
+    let after_tag = &html[html.find("<img")?..];
+    let tag_end = after_tag.find('>').map(|i| i + 1).unwrap_or(after_tag.len());
+    let tag = &after_tag[..tag_end];
+
+    let mut search_from = 0;
+    loop {
+        let at = search_from + tag[search_from..].find("src=")?;
+        let starts_attribute = tag[..at].chars().next_back().map_or(true, |c| c.is_whitespace());
+        if !starts_attribute {
+            search_from = at + "src=".len();
+            continue;
+        }
 
-    let items_to_send = match last_seen_option {
-        Some(&last_seen) => {
-            let mut newer: Vec<_> = items_with_dates
-                .into_iter()
-                .filter(|(_, d)| *d > last_seen)
-                .collect();
-            newer.sort_by_key(|(_, d)| *d);
-            newer
+        let after_src = &tag[at + "src=".len()..];
+        let quote = after_src.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
         }
-        None => {
-            items_with_dates.sort_by(|a, b| b.1.cmp(&a.1));
-            let mut top_3: Vec<_> = items_with_dates.into_iter().take(3).collect();
-            top_3.reverse();
-            top_3
+        let value = &after_src[quote.len_utf8()..];
+        let end = value.find(quote)?;
+        return Some(value[..end].to_string());
+    }
+}
+
+/// Returns the item's `guid`, falling back to its link when `guid` is absent.
+fn item_guid(item: &rss::Item) -> String {
+    item.guid()
+        .map(|guid| guid.value().to_string())
+        .or_else(|| item.link().map(|link| link.to_string()))
+        .unwrap_or_default()
+}
+
+/// Renders `format` via `strfmt`, falling back to `default_format` and
+/// logging the error when a feed's custom template is malformed.
+fn render_template(format: &str, args: &HashMap<String, String>, default_format: &str, what: &str) -> String {
+    strfmt(format, args).unwrap_or_else(|e| {
+        eprintln!("Invalid {} {:?}: {}", what, format, e);
+        strfmt(default_format, args).unwrap_or_default()
+    })
+}
+
+/// Waits out any remaining per-webhook rate-limit window before letting the
+/// caller send, since several feeds can share one webhook.
+fn wait_for_rate_limit(last_sent: &mut HashMap<String, Instant>, webhook_url: &str) {
+    if let Some(&last) = last_sent.get(webhook_url) {
+        let elapsed = last.elapsed();
+        if elapsed < DISCORD_RATE_LIMIT {
+            thread::sleep(DISCORD_RATE_LIMIT - elapsed);
         }
+    }
+    last_sent.insert(webhook_url.to_string(), Instant::now());
+}
+
+/// Whether `guid` is already tracked for a feed.
+fn seen_guids_contains(seen_guids: &[SeenGuid], guid: &str) -> bool {
+    seen_guids.iter().any(|seen| seen.guid == guid)
+}
+
+/// Drops the oldest tracked GUIDs once a feed's history grows past `cap`.
+/// Dated entries are pruned earliest-date-first; entries without a date
+/// carry no freshness signal of their own, so they're only evicted once no
+/// dated entry remains, oldest-inserted first. This keeps a still-current
+/// GUID from being evicted by the arbitrary order a `HashSet` would give,
+/// which would otherwise cause it to be re-announced as new.
+fn evict_oldest_guids(seen_guids: &mut Vec<SeenGuid>, cap: usize) {
+    while seen_guids.len() > cap {
+        let evict_index = seen_guids
+            .iter()
+            .enumerate()
+            .filter(|(_, seen)| seen.date.is_some())
+            .min_by_key(|(_, seen)| seen.date)
+            .map(|(index, _)| index)
+            .unwrap_or(0);
+        seen_guids.remove(evict_index);
+    }
+}
+
+/// Applies a fetched feed to `state`: determines novel items, dispatches them
+/// to Discord, and updates dedup/aggregation bookkeeping. This is the single
+/// writer feeding off the worker pool's results, and is source-agnostic —
+/// it only ever sees normalized `NewsItem`s. Items are kept even when a feed
+/// omits a date entirely; novelty then rests solely on the GUID/link already
+/// tracked in `seen_guids`.
+fn process_feed_items(
+    feed_config: &FeedConfig,
+    fetched: FetchedFeed,
+    state: &mut AppState,
+    webhook_url: &str,
+    aggregated: &AggregatedFeeds,
+    last_sent: &mut HashMap<String, Instant>,
+) -> Result<bool, Box<dyn Error>> {
+    let items = fetched.items;
+
+    update_aggregated_feed(aggregated, feed_config, &items);
+
+    let last_seen_option = state.last_seen.get(&feed_config.url).cloned();
+    let seen_guids = state.seen_guids.entry(feed_config.url.clone()).or_default();
+    let is_first_poll = seen_guids.is_empty();
+
+    let items_to_send: Vec<NewsItem> = if is_first_poll {
+        let mut sorted = items.clone();
+        sorted.sort_by(|a, b| b.date.cmp(&a.date));
+        let mut top_3: Vec<_> = sorted.into_iter().take(3).collect();
+        top_3.reverse();
+        top_3
+    } else {
+        let mut newer: Vec<NewsItem> = items
+            .iter()
+            .cloned()
+            .filter(|item| !seen_guids_contains(seen_guids, &item.guid))
+            .collect();
+        newer.sort_by_key(|item| item.date);
+        newer
     };
 
-    if items_to_send.is_empty() {
-        return Ok(false);
+    // Record every GUID seen in this fetch, not just the ones sent, so items
+    // skipped on the first poll are never flagged as new later.
+    let mut guids_changed = false;
+    for item in &items {
+        if !seen_guids_contains(seen_guids, &item.guid) {
+            seen_guids.push(SeenGuid {
+                guid: item.guid.clone(),
+                date: item.date,
+            });
+            guids_changed = true;
+        }
     }
+    evict_oldest_guids(seen_guids, MAX_SEEN_GUIDS_PER_FEED);
 
-    let mut current_max_date = last_seen_option.cloned().unwrap_or(items_to_send[0].1);
+    if items_to_send.is_empty() {
+        return Ok(guids_changed);
+    }
 
     for chunk in items_to_send.chunks(10) {
-        send_discord_batch(chunk, &channel.title, feed_config.color, webhook_url)?;
+        wait_for_rate_limit(last_sent, webhook_url);
+        send_discord_batch(chunk, &fetched.title, feed_config, webhook_url)?;
+    }
 
-        if let Some((_, date)) = chunk.last() {
-            if *date > current_max_date {
-                current_max_date = *date;
-            }
+    if let Some(max_date) = items_to_send.iter().filter_map(|item| item.date).max() {
+        let is_newer = last_seen_option.map_or(true, |prev| max_date > prev);
+        if is_newer {
+            state.last_seen.insert(feed_config.url.clone(), max_date);
         }
-
-        thread::sleep(Duration::from_millis(1000));
     }
-
-    state
-        .last_seen
-        .insert(feed_config.url.to_string(), current_max_date);
     Ok(true)
 }
 
 fn send_discord_batch(
-    items: &[(&rss::Item, DateTime<FixedOffset>)],
+    items: &[NewsItem],
     feed_title: &str,
-    color: u32,
+    feed_config: &FeedConfig,
     webhook_url: &str,
 ) -> Result<(), Box<dyn Error>> {
     let mut embeds = Vec::new();
 
-    for (item, date) in items {
-        let title = item.title().unwrap_or("No Title");
-        let link = item.link().unwrap_or("");
-        let raw_desc = item.description().unwrap_or("No description");
-
-        let mut cleaned_desc = String::with_capacity(raw_desc.len());
-        let mut inside_tag = false;
-        for c in raw_desc.chars() {
-            match c {
-                '<' => inside_tag = true,
-                '>' => inside_tag = false,
-                _ if !inside_tag => cleaned_desc.push(c),
-                _ => {}
-            }
-        }
+    for item in items {
+        let date = item.date;
+        let image = extract_first_image(&item.description).map(|url| DiscordEmbedImage { url });
 
-        let final_desc = cleaned_desc.replace("&nbsp;", " ").trim().to_string();
-        let description = if final_desc.len() > 200 {
-            Cow::Owned(format!("{}...", &final_desc[0..200]))
+        let final_desc = clean_html(&item.description);
+        let description = if final_desc.chars().count() > DESCRIPTION_MAX_CHARS {
+            let truncated: String = final_desc.chars().take(DESCRIPTION_MAX_CHARS).collect();
+            format!("{}...", truncated)
         } else {
-            Cow::Owned(final_desc)
+            final_desc
         };
 
+        let mut template_args = HashMap::new();
+        template_args.insert("title".to_string(), item.title.clone());
+        template_args.insert("feed".to_string(), feed_title.to_string());
+        template_args.insert("author".to_string(), item.author.clone());
+        template_args.insert("link".to_string(), item.link.clone());
+        template_args.insert(
+            "date".to_string(),
+            date.map(|date| date.to_rfc3339()).unwrap_or_default(),
+        );
+        template_args.insert("description".to_string(), description);
+
+        let rendered_title = render_template(
+            &feed_config.title_format,
+            &template_args,
+            DEFAULT_TITLE_FORMAT,
+            "title_format",
+        );
+        let rendered_description = render_template(
+            &feed_config.description_format,
+            &template_args,
+            DEFAULT_DESCRIPTION_FORMAT,
+            "description_format",
+        );
+
         embeds.push(DiscordEmbed {
-            title: Cow::Borrowed(title),
-            url: Cow::Borrowed(link),
-            description,
-            color,
+            title: Cow::Owned(rendered_title),
+            url: Cow::Owned(item.link.clone()),
+            description: Cow::Owned(rendered_description),
+            color: feed_config.color,
             footer: DiscordFooter {
                 text: Cow::Owned(format!("{}", feed_title)),
             },
-            timestamp: date.to_rfc3339(),
+            timestamp: date.map(|date| date.to_rfc3339()),
+            image,
         });
     }
 
     let payload = DiscordMessage {
-        username: Cow::Borrowed("Arch Linux Bot"),
+        username: feed_config
+            .username
+            .as_deref()
+            .map(Cow::Borrowed)
+            .unwrap_or(Cow::Borrowed("Arch Linux Bot")),
+        avatar_url: feed_config.avatar_url.as_deref().map(Cow::Borrowed),
         embeds,
     };
 